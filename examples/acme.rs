@@ -6,11 +6,19 @@ fn main() {
         .contact_push("mailto:admin@example.org")
         .cache(DirCache::new("/srv/example/acme-cache-dir"));
 
-    let (acceptor, future) = trillium_acme::new(config);
+    let (acceptor, mut events, cert_status) = trillium_acme::new(config);
     let stopper = trillium_smol::Stopper::new();
-    let future = stopper.stop_future(future);
-    trillium_smol::spawn(async {
-        future.await;
+    trillium_smol::spawn(stopper.stop_future(async move {
+        while let Some(event) = futures_lite::StreamExt::next(&mut events).await {
+            match event {
+                Ok(ok) => trillium::log::info!("ACME event: {:?}", ok),
+                Err(err) => trillium::log::error!("ACME error: {:?}", err),
+            }
+        }
+    }));
+    trillium_smol::spawn(async move {
+        cert_status.ready().await;
+        trillium::log::info!("first certificate is cached and ready to serve");
     });
     trillium_smol::config()
         .with_port(443)
@@ -0,0 +1,364 @@
+//! Minimal internal ACME order state machine, used by the [`crate::dns01`] and [`crate::http01`]
+//! challenge flows. `rustls_acme` only speaks tls-alpn-01 directly, so these flows drive their
+//! own orders against the ACME directory rather than going through [`crate::AcmeConfig`].
+
+use std::fmt;
+use std::future::IntoFuture;
+
+use ring::rand::SystemRandom;
+use ring::signature::{EcdsaKeyPair, EcdsaSigningAlgorithm, KeyPair, ECDSA_P256_SHA256_FIXED_SIGNING};
+use serde_json::{json, Value};
+use trillium_client::{Client, Connector};
+
+const ALG: &EcdsaSigningAlgorithm = &ECDSA_P256_SHA256_FIXED_SIGNING;
+
+/// An error encountered while driving a DNS-01 or HTTP-01 ACME order.
+#[derive(Debug)]
+pub enum Error {
+    /// An HTTP request to the ACME server failed, or its response could not be parsed.
+    Http(String),
+    /// The ACME server returned an error response, or a resource that could not be used (one
+    /// that became invalid, lacks a required field, or offers no usable challenge).
+    Acme(String),
+    /// Generating or using the account key, certificate key, or CSR failed.
+    Crypto(String),
+    /// The configured challenge solver failed to publish or remove a challenge.
+    Solver(String),
+    /// An order or authorization did not become valid or invalid within the allotted number of
+    /// polls.
+    Timeout,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Http(message) => write!(f, "ACME http error: {message}"),
+            Error::Acme(message) => write!(f, "ACME server error: {message}"),
+            Error::Crypto(message) => write!(f, "ACME crypto error: {message}"),
+            Error::Solver(message) => write!(f, "challenge solver error: {message}"),
+            Error::Timeout => write!(f, "timed out waiting for ACME order to complete"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+pub(crate) fn base64url(bytes: &[u8]) -> String {
+    use base64::Engine;
+    base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(bytes)
+}
+
+pub(crate) fn sha256(bytes: &[u8]) -> [u8; 32] {
+    ring::digest::digest(&ring::digest::SHA256, bytes)
+        .as_ref()
+        .try_into()
+        .expect("SHA-256 digest is 32 bytes")
+}
+
+/// The key authorization for a challenge, per RFC 8555 §8.1: the challenge token, a period, and
+/// `account_thumbprint`, the account key's base64url-encoded JWK thumbprint (itself the SHA-256
+/// digest of the canonical JWK, as computed by [`Account::new`]).
+pub(crate) fn key_authorization(token: &str, account_thumbprint: &str) -> String {
+    format!("{token}.{account_thumbprint}")
+}
+
+#[derive(Clone)]
+struct DirectoryUrls {
+    new_nonce: String,
+    new_account: String,
+    new_order: String,
+}
+
+pub(crate) struct Order {
+    pub(crate) url: String,
+    pub(crate) authorizations: Vec<String>,
+    pub(crate) finalize: String,
+}
+
+/// An ACME account key, together with enough HTTP machinery to sign and send JWS requests on
+/// its behalf.
+pub(crate) struct Account<C> {
+    client: Client<C>,
+    directory_url: String,
+    key_pair: EcdsaKeyPair,
+    jwk: Value,
+    thumbprint: String,
+    kid: Option<String>,
+    nonce: Option<String>,
+    last_location: Option<String>,
+    directory: Option<DirectoryUrls>,
+}
+
+impl<C: Connector> Account<C> {
+    /// Generate a fresh account key for `directory_url`. The account is not registered until
+    /// [`Account::ensure_registered`] is called.
+    pub(crate) fn new(client: Client<C>, directory_url: String) -> Result<Self, Error> {
+        let rng = SystemRandom::new();
+        let pkcs8 = EcdsaKeyPair::generate_pkcs8(ALG, &rng)
+            .map_err(|e| Error::Crypto(format!("generating account key: {e}")))?;
+        let key_pair = EcdsaKeyPair::from_pkcs8(ALG, pkcs8.as_ref(), &rng)
+            .map_err(|e| Error::Crypto(format!("loading account key: {e}")))?;
+
+        // Uncompressed SEC1 point: 0x04 || X || Y, each coordinate 32 bytes for P-256.
+        let public_key = key_pair.public_key().as_ref();
+        let coordinate_len = (public_key.len() - 1) / 2;
+        let x = base64url(&public_key[1..1 + coordinate_len]);
+        let y = base64url(&public_key[1 + coordinate_len..]);
+        let jwk = json!({"crv": "P-256", "kty": "EC", "x": x, "y": y});
+        // RFC 7638: thumbprint input is the canonical JWK with lexicographically sorted keys.
+        let thumbprint_input = format!(r#"{{"crv":"P-256","kty":"EC","x":"{x}","y":"{y}"}}"#);
+        let thumbprint = base64url(&sha256(thumbprint_input.as_bytes()));
+
+        Ok(Self {
+            client,
+            directory_url,
+            key_pair,
+            jwk,
+            thumbprint,
+            kid: None,
+            nonce: None,
+            last_location: None,
+            directory: None,
+        })
+    }
+
+    pub(crate) fn thumbprint(&self) -> &str {
+        &self.thumbprint
+    }
+
+    async fn directory(&mut self) -> Result<DirectoryUrls, Error> {
+        if let Some(directory) = &self.directory {
+            return Ok(directory.clone());
+        }
+        let body = self
+            .client
+            .get(&self.directory_url)
+            .into_future()
+            .await
+            .map_err(http_err)?
+            .response_body()
+            .read_string()
+            .await
+            .map_err(http_err)?;
+        let body: Value = serde_json::from_str(&body).map_err(http_err)?;
+        let directory = DirectoryUrls {
+            new_nonce: str_field(&body, "newNonce")?,
+            new_account: str_field(&body, "newAccount")?,
+            new_order: str_field(&body, "newOrder")?,
+        };
+        self.directory = Some(directory.clone());
+        Ok(directory)
+    }
+
+    async fn fresh_nonce(&mut self) -> Result<String, Error> {
+        if let Some(nonce) = self.nonce.take() {
+            return Ok(nonce);
+        }
+        let new_nonce = self.directory().await?.new_nonce;
+        let mut conn = self
+            .client
+            .head(&new_nonce)
+            .into_future()
+            .await
+            .map_err(http_err)?;
+        conn.response_headers()
+            .get_str("replay-nonce")
+            .map(String::from)
+            .ok_or_else(|| Error::Http("directory newNonce response missing replay-nonce".into()))
+    }
+
+    /// Sign `payload` (or send an empty POST-as-GET if `payload` is `None`) as a flattened JWS
+    /// and POST it to `url`, returning the parsed JSON response body.
+    async fn post(&mut self, url: &str, payload: Option<&Value>) -> Result<Value, Error> {
+        let nonce = self.fresh_nonce().await?;
+        let protected = match &self.kid {
+            Some(kid) => json!({"alg": "ES256", "nonce": nonce, "url": url, "kid": kid}),
+            None => json!({"alg": "ES256", "nonce": nonce, "url": url, "jwk": self.jwk}),
+        };
+        let protected = base64url(serde_json::to_string(&protected).map_err(http_err)?.as_bytes());
+        let payload = base64url(
+            payload
+                .map(serde_json::to_string)
+                .transpose()
+                .map_err(http_err)?
+                .unwrap_or_default()
+                .as_bytes(),
+        );
+        let signing_input = format!("{protected}.{payload}");
+        let rng = SystemRandom::new();
+        let signature = self
+            .key_pair
+            .sign(&rng, signing_input.as_bytes())
+            .map_err(|e| Error::Crypto(e.to_string()))?;
+        let body = json!({
+            "protected": protected,
+            "payload": payload,
+            "signature": base64url(signature.as_ref()),
+        });
+
+        let mut conn = self
+            .client
+            .post(url)
+            .with_body(serde_json::to_vec(&body).map_err(http_err)?)
+            .with_header("content-type", "application/jose+json")
+            .into_future()
+            .await
+            .map_err(http_err)?;
+
+        if let Some(nonce) = conn.response_headers().get_str("replay-nonce") {
+            self.nonce = Some(nonce.to_string());
+        }
+        self.last_location = conn
+            .response_headers()
+            .get_str("location")
+            .map(String::from);
+        if self.kid.is_none() {
+            if let Some(location) = &self.last_location {
+                self.kid = Some(location.clone());
+            }
+        }
+
+        let status = conn.status().map(|status| status as u16).unwrap_or(0);
+        let body = conn.response_body().read_string().await.map_err(http_err)?;
+        if status >= 400 {
+            return Err(Error::Acme(body));
+        }
+        if body.is_empty() {
+            return Ok(Value::Null);
+        }
+        serde_json::from_str(&body).map_err(http_err)
+    }
+
+    /// Make sure the account is registered. Safe to call more than once; it's a no-op once a
+    /// `kid` has been assigned.
+    pub(crate) async fn ensure_registered(&mut self, contact: &[String]) -> Result<(), Error> {
+        if self.kid.is_some() {
+            return Ok(());
+        }
+        let new_account = self.directory().await?.new_account;
+        let payload = json!({"termsOfServiceAgreed": true, "contact": contact});
+        self.post(&new_account, Some(&payload)).await?;
+        Ok(())
+    }
+
+    pub(crate) async fn new_order(&mut self, domains: &[String]) -> Result<Order, Error> {
+        let new_order = self.directory().await?.new_order;
+        let identifiers: Vec<Value> = domains
+            .iter()
+            .map(|domain| json!({"type": "dns", "value": domain}))
+            .collect();
+        let body = self
+            .post(&new_order, Some(&json!({"identifiers": identifiers})))
+            .await?;
+        Ok(Order {
+            url: self.last_location.take().unwrap_or_default(),
+            authorizations: body["authorizations"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(|value| value.as_str().map(String::from))
+                .collect(),
+            finalize: str_field(&body, "finalize")?,
+        })
+    }
+
+    /// POST-as-GET the resource at `url`, for fetching authorizations and orders.
+    pub(crate) async fn get(&mut self, url: &str) -> Result<Value, Error> {
+        self.post(url, None).await
+    }
+
+    pub(crate) async fn respond_to_challenge(&mut self, challenge_url: &str) -> Result<(), Error> {
+        self.post(challenge_url, Some(&json!({}))).await?;
+        Ok(())
+    }
+
+    /// Poll `url` (an authorization or order resource) until its `status` field becomes `valid`,
+    /// returning the final resource, or `invalid`/timeout as an error.
+    pub(crate) async fn poll_until_valid(&mut self, url: &str) -> Result<Value, Error> {
+        for _ in 0..40 {
+            let resource = self.get(url).await?;
+            match resource["status"].as_str() {
+                Some("valid") => return Ok(resource),
+                Some("invalid") => {
+                    return Err(Error::Acme(format!("resource became invalid: {resource}")))
+                }
+                _ => {}
+            }
+            async_io::Timer::after(std::time::Duration::from_millis(500)).await;
+        }
+        Err(Error::Timeout)
+    }
+
+    pub(crate) async fn finalize(
+        &mut self,
+        finalize_url: &str,
+        csr_der: &[u8],
+    ) -> Result<Value, Error> {
+        self.post(finalize_url, Some(&json!({"csr": base64url(csr_der)})))
+            .await
+    }
+
+    pub(crate) async fn download_certificate(&mut self, certificate_url: &str) -> Result<Vec<u8>, Error> {
+        let mut conn = self
+            .client
+            .post(certificate_url)
+            .with_body(self.signed_post_as_get_body(certificate_url).await?)
+            .with_header("content-type", "application/jose+json")
+            .into_future()
+            .await
+            .map_err(http_err)?;
+        conn.response_body()
+            .read_bytes()
+            .await
+            .map(<[_]>::to_vec)
+            .map_err(http_err)
+    }
+
+    async fn signed_post_as_get_body(&mut self, url: &str) -> Result<Vec<u8>, Error> {
+        let nonce = self.fresh_nonce().await?;
+        let kid = self
+            .kid
+            .clone()
+            .ok_or_else(|| Error::Acme("account is not yet registered".into()))?;
+        let protected = base64url(
+            json!({"alg": "ES256", "nonce": nonce, "url": url, "kid": kid})
+                .to_string()
+                .as_bytes(),
+        );
+        let signing_input = format!("{protected}.");
+        let rng = SystemRandom::new();
+        let signature = self
+            .key_pair
+            .sign(&rng, signing_input.as_bytes())
+            .map_err(|e| Error::Crypto(e.to_string()))?;
+        serde_json::to_vec(&json!({
+            "protected": protected,
+            "payload": "",
+            "signature": base64url(signature.as_ref()),
+        }))
+        .map_err(http_err)
+    }
+}
+
+/// Generate a fresh certificate key pair and a CSR for `domains`, returning `(csr_der,
+/// private_key_der)`. Shared by the DNS-01 and HTTP-01 order flows.
+pub(crate) fn generate_csr(domains: &[String]) -> Result<(Vec<u8>, Vec<u8>), Error> {
+    let params = rcgen::CertificateParams::new(domains.to_vec())
+        .map_err(|e| Error::Crypto(e.to_string()))?;
+    let key_pair = rcgen::KeyPair::generate().map_err(|e| Error::Crypto(e.to_string()))?;
+    let csr = params
+        .serialize_request(&key_pair)
+        .map_err(|e| Error::Crypto(e.to_string()))?;
+    Ok((csr.der().to_vec(), key_pair.serialize_der()))
+}
+
+fn str_field(value: &Value, field: &str) -> Result<String, Error> {
+    value[field]
+        .as_str()
+        .map(String::from)
+        .ok_or_else(|| Error::Http(format!("ACME response missing `{field}`")))
+}
+
+fn http_err(e: impl fmt::Display) -> Error {
+    Error::Http(e.to_string())
+}
@@ -0,0 +1,230 @@
+//! DNS-01 challenge support, for obtaining certificates that tls-alpn-01 cannot — most notably
+//! wildcard certificates such as `*.example.com`. `rustls_acme`'s [`AcmeConfig`][crate::AcmeConfig]
+//! only implements tls-alpn-01, so [`Dns01Config`] drives its own minimal ACME order (see
+//! [`crate::acme`]) and delegates publishing the challenge record to a [`DnsSolver`] you provide.
+
+use std::fmt;
+
+use trillium_client::{Client, Connector};
+
+use crate::acme::{self, Account};
+
+/// The error type returned by [`DnsSolver`] methods.
+pub type DnsSolverError = Box<dyn std::error::Error + Send + Sync>;
+
+/// A pluggable DNS provider for publishing and removing the TXT records used to complete ACME
+/// DNS-01 challenges ([RFC 8555 §8.4](https://www.rfc-editor.org/rfc/rfc8555#section-8.4)).
+///
+/// Implement this against whatever DNS provider you use. `fqdn` is always the full
+/// `_acme-challenge.<base-domain>` name to publish the record under, already computed for you
+/// (including stripping the `*.` label for wildcard domains).
+#[trillium::async_trait]
+pub trait DnsSolver: fmt::Debug + Send + Sync {
+    /// Create or update the TXT record at `fqdn` so that it contains exactly `value`, replacing
+    /// any value left over from a previous validation attempt.
+    async fn upsert_txt(&self, fqdn: &str, value: &str) -> Result<(), DnsSolverError>;
+
+    /// Remove the TXT record previously published at `fqdn` via [`DnsSolver::upsert_txt`].
+    async fn remove_txt(&self, fqdn: &str) -> Result<(), DnsSolverError>;
+}
+
+/// The name the `_acme-challenge` TXT record must be published under for `domain`: the domain
+/// itself, or its base domain when `domain` is a wildcard like `*.example.com`.
+fn challenge_fqdn(domain: &str) -> String {
+    format!(
+        "_acme-challenge.{}",
+        domain.strip_prefix("*.").unwrap_or(domain)
+    )
+}
+
+/// Configuration for obtaining a certificate via the DNS-01 challenge.
+///
+/// Mirrors the shape of [`AcmeConfig`][crate::AcmeConfig]: start with [`Dns01Config::new`], chain
+/// on [`Dns01Config::contact_push`] and [`Dns01Config::directory_lets_encrypt`] as needed, then
+/// call [`Dns01Config::obtain_certificate`].
+pub struct Dns01Config<C, S> {
+    client: Client<C>,
+    directory_url: String,
+    domains: Vec<String>,
+    contact: Vec<String>,
+    solver: S,
+}
+
+impl<C, S: fmt::Debug> fmt::Debug for Dns01Config<C, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Dns01Config")
+            .field("directory_url", &self.directory_url)
+            .field("domains", &self.domains)
+            .field("contact", &self.contact)
+            .field("solver", &self.solver)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<C: Connector, S: DnsSolver> Dns01Config<C, S> {
+    /// Start configuring a DNS-01 order for `domains` (which may include wildcards like
+    /// `*.example.com`), using `client` to reach the ACME directory and `solver` to publish
+    /// challenge records. Defaults to the Let's Encrypt staging directory.
+    pub fn new(
+        client: Client<C>,
+        domains: impl IntoIterator<Item = impl Into<String>>,
+        solver: S,
+    ) -> Self {
+        Self {
+            client,
+            directory_url: rustls_acme::LETS_ENCRYPT_STAGING_DIRECTORY.to_string(),
+            domains: domains.into_iter().map(Into::into).collect(),
+            contact: Vec::new(),
+            solver,
+        }
+    }
+
+    /// Add a contact URL, such as `"mailto:admin@example.org"`, to the ACME account created for
+    /// this order.
+    pub fn contact_push(mut self, contact: impl Into<String>) -> Self {
+        self.contact.push(contact.into());
+        self
+    }
+
+    /// Use a specific ACME directory URL, such as a private CA used in tests.
+    pub fn directory(mut self, directory_url: impl Into<String>) -> Self {
+        self.directory_url = directory_url.into();
+        self
+    }
+
+    /// Switch between the Let's Encrypt production and staging directories.
+    pub fn directory_lets_encrypt(mut self, production: bool) -> Self {
+        self.directory_url = if production {
+            rustls_acme::LETS_ENCRYPT_PRODUCTION_DIRECTORY
+        } else {
+            rustls_acme::LETS_ENCRYPT_STAGING_DIRECTORY
+        }
+        .to_string();
+        self
+    }
+
+    /// Run a single DNS-01 validation pass against every configured domain and return
+    /// `(certificate_chain_pem, private_key_der)`: the signed certificate chain as returned by
+    /// the ACME server (PEM-encoded), and its private key (DER-encoded).
+    ///
+    /// This registers a fresh ACME account on each call; callers that need to renew a
+    /// certificate are expected to call this again before it expires and to persist the result
+    /// themselves, the same way a [`rustls_acme`] cache would.
+    pub async fn obtain_certificate(&self) -> Result<(Vec<u8>, Vec<u8>), acme::Error> {
+        let mut account = Account::new(self.client.clone(), self.directory_url.clone())?;
+        account.ensure_registered(&self.contact).await?;
+        let order = account.new_order(&self.domains).await?;
+
+        for authorization_url in &order.authorizations {
+            self.complete_dns01_authorization(&mut account, authorization_url)
+                .await?;
+        }
+
+        let (csr_der, private_key_der) = acme::generate_csr(&self.domains)?;
+        account.finalize(&order.finalize, &csr_der).await?;
+        let order = account.poll_until_valid(&order.url).await?;
+        let certificate_url = order["certificate"]
+            .as_str()
+            .ok_or_else(|| acme::Error::Acme("finalized order missing certificate url".into()))?;
+        let certificate_pem = account.download_certificate(certificate_url).await?;
+        Ok((certificate_pem, private_key_der))
+    }
+
+    async fn complete_dns01_authorization(
+        &self,
+        account: &mut Account<C>,
+        authorization_url: &str,
+    ) -> Result<(), acme::Error> {
+        let authorization = account.get(authorization_url).await?;
+        let domain = authorization["identifier"]["value"]
+            .as_str()
+            .ok_or_else(|| acme::Error::Acme("authorization missing identifier".into()))?
+            .to_string();
+        let challenge = authorization["challenges"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .find(|challenge| challenge["type"] == "dns-01")
+            .ok_or_else(|| acme::Error::Acme("no dns-01 challenge offered".into()))?;
+        let token = challenge["token"]
+            .as_str()
+            .ok_or_else(|| acme::Error::Acme("dns-01 challenge missing token".into()))?;
+        let challenge_url = challenge["url"]
+            .as_str()
+            .ok_or_else(|| acme::Error::Acme("dns-01 challenge missing url".into()))?
+            .to_string();
+
+        let key_authorization = acme::key_authorization(token, account.thumbprint());
+        let txt_value = acme::base64url(&acme::sha256(key_authorization.as_bytes()));
+        let fqdn = challenge_fqdn(&domain);
+
+        self.solver
+            .upsert_txt(&fqdn, &txt_value)
+            .await
+            .map_err(|e| acme::Error::Solver(e.to_string()))?;
+
+        let result = async {
+            account.respond_to_challenge(&challenge_url).await?;
+            account.poll_until_valid(authorization_url).await
+        }
+        .await;
+
+        // Best-effort cleanup: a `remove_txt` failure here shouldn't discard an otherwise
+        // successful validation, so log it rather than propagating it over `result`.
+        if let Err(e) = self.solver.remove_txt(&fqdn).await {
+            trillium::log::warn!("failed to remove {fqdn} TXT record after DNS-01 validation: {e}");
+        }
+
+        result.map(|_| ())
+    }
+}
+
+/// A [`DnsSolver`] backed by
+/// [pebble-challtestsrv](https://github.com/letsencrypt/pebble/tree/main/cmd/pebble-challtestsrv)'s
+/// DNS management HTTP API, reached with plain HTTP requests by `client`. This is a reference
+/// implementation of the trait for testing against a local Pebble ACME server (see
+/// `tests/test-dns01-with-pebble.rs`);
+/// it is not meant for use against a production DNS provider, since pebble-challtestsrv serves
+/// every configured record to any resolver rather than actually updating a zone.
+#[derive(Debug)]
+pub struct PebbleDnsSolver<C> {
+    client: Client<C>,
+    management_url: String,
+}
+
+impl<C: Connector> PebbleDnsSolver<C> {
+    /// Create a solver that manages records via pebble-challtestsrv's management API at
+    /// `management_url` (its `-management` listen address, e.g. `http://127.0.0.1:8055`).
+    pub fn new(client: Client<C>, management_url: impl Into<String>) -> Self {
+        Self {
+            client,
+            management_url: management_url.into(),
+        }
+    }
+}
+
+#[trillium::async_trait]
+impl<C: Connector> DnsSolver for PebbleDnsSolver<C> {
+    async fn upsert_txt(&self, fqdn: &str, value: &str) -> Result<(), DnsSolverError> {
+        use std::future::IntoFuture;
+        let body = serde_json::json!({"host": format!("{fqdn}."), "value": value}).to_string();
+        self.client
+            .post(format!("{}/set-txt", self.management_url))
+            .with_body(body)
+            .into_future()
+            .await?;
+        Ok(())
+    }
+
+    async fn remove_txt(&self, fqdn: &str) -> Result<(), DnsSolverError> {
+        use std::future::IntoFuture;
+        let body = serde_json::json!({"host": format!("{fqdn}.")}).to_string();
+        self.client
+            .post(format!("{}/clear-txt", self.management_url))
+            .with_body(body)
+            .into_future()
+            .await?;
+        Ok(())
+    }
+}
+
@@ -0,0 +1,291 @@
+//! HTTP-01 challenge support, for deployments where tls-alpn-01 can't reach the server (for
+//! example, because TLS is terminated upstream), plus a redirect-to-HTTPS handler to run
+//! alongside it on port 80.
+//!
+//! [`Http01Tokens`] is the shared state: [`Http01Config::obtain_certificate`] publishes the key
+//! authorization for each pending challenge into it, and [`http_challenge_handler`] reads it back
+//! to answer `GET /.well-known/acme-challenge/<token>` requests.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use trillium::{Conn, Handler};
+use trillium_client::{Client, Connector};
+
+use crate::acme::{self, Account};
+
+const CHALLENGE_PATH_PREFIX: &str = "/.well-known/acme-challenge/";
+
+/// Key authorizations for HTTP-01 challenges currently being validated, shared between
+/// [`Http01Config`] and [`http_challenge_handler`].
+#[derive(Clone, Debug, Default)]
+pub struct Http01Tokens {
+    key_authorizations: Arc<Mutex<HashMap<String, String>>>,
+}
+
+impl Http01Tokens {
+    /// Create an empty token store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn insert(&self, token: String, key_authorization: String) {
+        self.key_authorizations
+            .lock()
+            .unwrap()
+            .insert(token, key_authorization);
+    }
+
+    fn remove(&self, token: &str) {
+        self.key_authorizations.lock().unwrap().remove(token);
+    }
+
+    fn get(&self, token: &str) -> Option<String> {
+        self.key_authorizations.lock().unwrap().get(token).cloned()
+    }
+}
+
+/// Build a Trillium handler that serves pending HTTP-01 challenges published to `tokens` and
+/// permanently redirects every other request to its `https://` equivalent.
+///
+/// Run this on a plain HTTP listener on port 80 alongside your HTTPS listener, sharing the same
+/// [`Http01Tokens`] passed to the [`Http01Config`] driving certificate acquisition:
+///
+/// ```rust,no_run
+/// # async fn doc() {
+/// let tokens = trillium_acme::Http01Tokens::new();
+/// trillium_smol::config()
+///     .with_port(80)
+///     .run(trillium_acme::http_challenge_handler(tokens));
+/// # }
+/// ```
+pub fn http_challenge_handler(tokens: Http01Tokens) -> impl Handler {
+    move |conn: Conn| {
+        let tokens = tokens.clone();
+        async move {
+            if conn.method() == trillium::Method::Get {
+                if let Some(token) = conn.path().strip_prefix(CHALLENGE_PATH_PREFIX) {
+                    if let Some(key_authorization) = tokens.get(token) {
+                        return conn.ok(key_authorization);
+                    }
+                }
+            }
+
+            // Strip any port from the Host header (":80" on this plain-HTTP listener would
+            // otherwise end up in the `https://` location verbatim).
+            let host = strip_host_port(conn.headers().get_str("host").unwrap_or_default());
+            let path = conn.path();
+            let query = conn.querystring();
+            let location = if query.is_empty() {
+                format!("https://{host}{path}")
+            } else {
+                format!("https://{host}{path}?{query}")
+            };
+            conn.with_status(301).with_header("location", location)
+        }
+    }
+}
+
+/// Strip a trailing `:port` from a `Host` header value, without mistaking the colons inside a
+/// bracketed IPv6 literal (e.g. `[::1]:80`) for the port separator.
+fn strip_host_port(host: &str) -> &str {
+    if host.starts_with('[') {
+        // Bracketed IPv6 literal: `[<addr>]` or `[<addr>]:<port>`. Either way, the literal
+        // itself ends at the closing bracket.
+        return match host.find(']') {
+            Some(end) => &host[..=end],
+            None => host,
+        };
+    }
+
+    // Not bracketed, so more than one colon means this is a bare IPv6 literal with no port
+    // (RFC 3986 requires brackets to combine one with a port), which we leave untouched.
+    if host.matches(':').count() != 1 {
+        return host;
+    }
+    match host.rsplit_once(':') {
+        Some((name, port)) if !port.is_empty() && port.bytes().all(|b| b.is_ascii_digit()) => name,
+        _ => host,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use trillium_testing::prelude::*;
+
+    #[test]
+    fn strips_port_from_plain_host() {
+        assert_eq!(strip_host_port("example.com:80"), "example.com");
+        assert_eq!(strip_host_port("example.com"), "example.com");
+    }
+
+    #[test]
+    fn strips_port_from_bracketed_ipv6_host() {
+        assert_eq!(strip_host_port("[::1]:80"), "[::1]");
+        assert_eq!(strip_host_port("[::1]"), "[::1]");
+    }
+
+    #[test]
+    fn leaves_bare_ipv6_host_untouched() {
+        assert_eq!(strip_host_port("::1"), "::1");
+    }
+
+    #[test]
+    fn challenge_hit_serves_key_authorization() {
+        let tokens = Http01Tokens::new();
+        tokens.insert("the-token".into(), "the-token.thumbprint".into());
+        let handler = http_challenge_handler(tokens);
+        assert_ok!(
+            get("/.well-known/acme-challenge/the-token").on(&handler),
+            "the-token.thumbprint"
+        );
+    }
+
+    #[test]
+    fn other_requests_redirect_to_https_preserving_querystring() {
+        let handler = http_challenge_handler(Http01Tokens::new());
+        let conn = get("/foo?a=1")
+            .with_request_header("host", "example.com")
+            .on(&handler);
+        assert_status!(conn, 301);
+        assert_header!(conn, "location", "https://example.com/foo?a=1");
+    }
+
+    #[test]
+    fn redirect_strips_host_port() {
+        let handler = http_challenge_handler(Http01Tokens::new());
+        let conn = get("/").with_request_header("host", "[::1]:80").on(&handler);
+        assert_status!(conn, 301);
+        assert_header!(conn, "location", "https://[::1]/");
+    }
+}
+
+/// Configuration for obtaining a certificate via the HTTP-01 challenge.
+///
+/// Mirrors [`crate::Dns01Config`]: start with [`Http01Config::new`], chain on
+/// [`Http01Config::contact_push`] and [`Http01Config::directory_lets_encrypt`] as needed, then
+/// call [`Http01Config::obtain_certificate`]. The [`Http01Tokens`] passed in must also be handed
+/// to [`http_challenge_handler`] so it can answer the challenges this publishes.
+pub struct Http01Config<C> {
+    client: Client<C>,
+    directory_url: String,
+    domains: Vec<String>,
+    contact: Vec<String>,
+    tokens: Http01Tokens,
+}
+
+impl<C> std::fmt::Debug for Http01Config<C> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Http01Config")
+            .field("directory_url", &self.directory_url)
+            .field("domains", &self.domains)
+            .field("contact", &self.contact)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<C: Connector> Http01Config<C> {
+    /// Start configuring an HTTP-01 order for `domains`, using `client` to reach the ACME
+    /// directory and `tokens` to publish challenges for [`http_challenge_handler`] to serve.
+    /// Defaults to the Let's Encrypt staging directory.
+    pub fn new(
+        client: Client<C>,
+        domains: impl IntoIterator<Item = impl Into<String>>,
+        tokens: Http01Tokens,
+    ) -> Self {
+        Self {
+            client,
+            directory_url: rustls_acme::LETS_ENCRYPT_STAGING_DIRECTORY.to_string(),
+            domains: domains.into_iter().map(Into::into).collect(),
+            contact: Vec::new(),
+            tokens,
+        }
+    }
+
+    /// Add a contact URL, such as `"mailto:admin@example.org"`, to the ACME account created for
+    /// this order.
+    pub fn contact_push(mut self, contact: impl Into<String>) -> Self {
+        self.contact.push(contact.into());
+        self
+    }
+
+    /// Use a specific ACME directory URL, such as a private CA used in tests.
+    pub fn directory(mut self, directory_url: impl Into<String>) -> Self {
+        self.directory_url = directory_url.into();
+        self
+    }
+
+    /// Switch between the Let's Encrypt production and staging directories.
+    pub fn directory_lets_encrypt(mut self, production: bool) -> Self {
+        self.directory_url = if production {
+            rustls_acme::LETS_ENCRYPT_PRODUCTION_DIRECTORY
+        } else {
+            rustls_acme::LETS_ENCRYPT_STAGING_DIRECTORY
+        }
+        .to_string();
+        self
+    }
+
+    /// Run a single HTTP-01 validation pass against every configured domain and return
+    /// `(certificate_chain_pem, private_key_der)`: the signed certificate chain as returned by
+    /// the ACME server (PEM-encoded), and its private key (DER-encoded).
+    ///
+    /// This registers a fresh ACME account on each call; callers that need to renew a
+    /// certificate are expected to call this again before it expires and to persist the result
+    /// themselves.
+    pub async fn obtain_certificate(&self) -> Result<(Vec<u8>, Vec<u8>), acme::Error> {
+        let mut account = Account::new(self.client.clone(), self.directory_url.clone())?;
+        account.ensure_registered(&self.contact).await?;
+        let order = account.new_order(&self.domains).await?;
+
+        for authorization_url in &order.authorizations {
+            self.complete_http01_authorization(&mut account, authorization_url)
+                .await?;
+        }
+
+        let (csr_der, private_key_der) = acme::generate_csr(&self.domains)?;
+        account.finalize(&order.finalize, &csr_der).await?;
+        let order = account.poll_until_valid(&order.url).await?;
+        let certificate_url = order["certificate"]
+            .as_str()
+            .ok_or_else(|| acme::Error::Acme("finalized order missing certificate url".into()))?;
+        let certificate_pem = account.download_certificate(certificate_url).await?;
+        Ok((certificate_pem, private_key_der))
+    }
+
+    async fn complete_http01_authorization(
+        &self,
+        account: &mut Account<C>,
+        authorization_url: &str,
+    ) -> Result<(), acme::Error> {
+        let authorization = account.get(authorization_url).await?;
+        let challenge = authorization["challenges"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .find(|challenge| challenge["type"] == "http-01")
+            .ok_or_else(|| acme::Error::Acme("no http-01 challenge offered".into()))?;
+        let token = challenge["token"]
+            .as_str()
+            .ok_or_else(|| acme::Error::Acme("http-01 challenge missing token".into()))?
+            .to_string();
+        let challenge_url = challenge["url"]
+            .as_str()
+            .ok_or_else(|| acme::Error::Acme("http-01 challenge missing url".into()))?
+            .to_string();
+
+        let key_authorization = acme::key_authorization(&token, account.thumbprint());
+        self.tokens.insert(token.clone(), key_authorization);
+
+        let result = async {
+            account.respond_to_challenge(&challenge_url).await?;
+            account.poll_until_valid(authorization_url).await
+        }
+        .await;
+
+        self.tokens.remove(&token);
+
+        result.map(|_| ())
+    }
+}
@@ -2,8 +2,9 @@
 //! certificates, via Let’s Encrypt and ACME tls-alpn-01 challenges.
 //!
 //! To use `trillium-acme`, create an [`AcmeConfig`] to configure the certificate you want, then
-//! call [`trillium_acme::new`] to create an [`Acceptor`] and a future. Spawn the future using the
-//! same stopper as the server, then pass the [`Acceptor`] to the server configuration:
+//! call [`trillium_acme::new`] to create an [`Acceptor`], a stream of [`AcmeEvent`]s, and a
+//! [`CertStatus`] handle. Spawn a future that drains the event stream using the same stopper as
+//! the server, then pass the [`Acceptor`] to the server configuration:
 //!
 //! ```rust,no_run
 //! use trillium_acme::AcmeConfig;
@@ -13,11 +14,19 @@
 //!     .contact_push("mailto:admin@example.org")
 //!     .cache(DirCache::new("/srv/example/acme-cache-dir"));
 //!
-//! let (acceptor, future) = trillium_acme::new(config);
+//! let (acceptor, mut events, cert_status) = trillium_acme::new(config);
 //! let stopper = trillium_smol::Stopper::new();
-//! let future = stopper.stop_future(future);
-//! trillium_smol::spawn(async {
-//!     future.await;
+//! trillium_smol::spawn(stopper.stop_future(async move {
+//!     while let Some(event) = futures_lite::StreamExt::next(&mut events).await {
+//!         match event {
+//!             Ok(ok) => trillium::log::info!("ACME event: {:?}", ok),
+//!             Err(err) => trillium::log::error!("ACME error: {:?}", err),
+//!         }
+//!     }
+//! }));
+//! trillium_smol::spawn(async move {
+//!     cert_status.ready().await;
+//!     trillium::log::info!("first certificate is cached and ready to serve");
 //! });
 //! trillium_smol::config()
 //!     .with_port(443)
@@ -54,6 +63,18 @@
 //! [stricter rate limits](https://letsencrypt.org/docs/rate-limits/).
 //!
 //! `trillium-acme` builds upon the [`rustls-acme`](https://crates.io/crates/rustls-acme) crate.
+//!
+//! [`new`] covers the common case; use [`Builder`] directly if you need to customize behavior,
+//! such as advertising `h2` via ALPN.
+//!
+//! tls-alpn-01 cannot obtain wildcard certificates (`*.example.com`). For those, use
+//! [`Dns01Config`] to drive a DNS-01 challenge instead, implementing [`DnsSolver`] against your
+//! DNS provider to publish the challenge record.
+//!
+//! If tls-alpn-01 can't reach your server at all (for example, because TLS is terminated
+//! upstream of it), use [`Http01Config`] to drive an HTTP-01 challenge instead, and run
+//! [`http_challenge_handler`] on a plain HTTP listener on port 80 to answer it and redirect
+//! everything else to HTTPS.
 
 #![forbid(unsafe_code)]
 #![deny(
@@ -68,11 +89,14 @@
 
 use std::fmt::Debug;
 use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
-use futures_lite::{AsyncWriteExt, StreamExt};
+use event_listener::Event;
+use futures_lite::{stream, AsyncWriteExt, Stream, StreamExt};
+use rustls_acme::futures_rustls::rustls::server::danger::ClientCertVerifier;
 use rustls_acme::futures_rustls::{rustls::ServerConfig, LazyConfigAcceptor};
-use trillium::log::{error, info};
+use rustls_acme::{EventError, EventOk};
 use trillium_server_common::async_trait;
 
 pub use rustls_acme::{self, AcmeConfig};
@@ -80,6 +104,65 @@ pub use rustls_acme::{self, AcmeConfig};
 mod transport;
 pub use transport::Transport;
 
+mod acme;
+pub use acme::Error as AcmeOrderError;
+
+mod dns01;
+pub use dns01::{Dns01Config, DnsSolver, DnsSolverError, PebbleDnsSolver};
+
+mod http01;
+pub use http01::{http_challenge_handler, Http01Config, Http01Tokens};
+
+/// An event produced while acquiring or renewing a certificate, as emitted by the stream returned
+/// from [`new`]. This is `Ok` for progress (such as an order being created or a certificate being
+/// deployed) and `Err` for a failure (such as a challenge being rejected), so that you can drive
+/// your own logging or metrics instead of being locked into the `trillium::log` macros.
+pub type AcmeEvent<EC, EA> = Result<EventOk, EventError<EC, EA>>;
+
+/// A cheaply-cloneable handle that reports whether [`new`] has cached a usable certificate yet.
+///
+/// This is useful for deferring readiness, such as flipping a load balancer health check to
+/// ready only once a certificate has actually been provisioned.
+#[derive(Clone, Debug)]
+pub struct CertStatus {
+    ready: Arc<AtomicBool>,
+    event: Arc<Event>,
+}
+
+impl CertStatus {
+    fn new() -> Self {
+        Self {
+            ready: Arc::new(AtomicBool::new(false)),
+            event: Arc::new(Event::new()),
+        }
+    }
+
+    fn set_ready(&self) {
+        self.ready.store(true, Ordering::Release);
+        self.event.notify(usize::MAX);
+    }
+
+    /// Returns true if a usable certificate has already been cached.
+    pub fn is_ready(&self) -> bool {
+        self.ready.load(Ordering::Acquire)
+    }
+
+    /// Waits until a usable certificate has been cached, resolving immediately if one already
+    /// has been by the time this is called.
+    pub async fn ready(&self) {
+        loop {
+            if self.is_ready() {
+                return;
+            }
+            let listener = self.event.listen();
+            if self.is_ready() {
+                return;
+            }
+            listener.await;
+        }
+    }
+}
+
 /// An acceptor that handles ACME tls-alpn-01 challenges.
 ///
 /// After processing a challenge, this acceptor will return a Transport representing a closed
@@ -90,31 +173,121 @@ pub struct Acceptor {
     default_server_config: Arc<ServerConfig>,
 }
 
-/// Create a new [`Acceptor`] to pass to [`trillium_server_common::Config::with_acceptor`], and a
-/// new future that must be spawned detached in the background.
-pub fn new<EC: 'static + Debug, EA: 'static + Debug>(
+/// A builder for configuring the [`Acceptor`] and related handles created by [`new`].
+///
+/// Use this instead of [`new`] when you need to customize behavior beyond the defaults, such as
+/// the ALPN protocols the TLS listener advertises.
+#[derive(Debug)]
+pub struct Builder<EC, EA> {
     config: AcmeConfig<EC, EA>,
-) -> (Acceptor, impl Future) {
-    let mut state = config.state();
-    let challenge_server_config = state.challenge_rustls_config();
-    let default_server_config = state.default_rustls_config();
+    alpn_protocols: Option<Vec<Vec<u8>>>,
+    client_cert_verifier: Option<Arc<dyn ClientCertVerifier>>,
+}
 
-    let future = async move {
-        loop {
-            match state.next().await.unwrap() {
-                Ok(ok) => info!("ACME event: {:?}", ok),
-                Err(err) => error!("ACME error: {:?}", err),
-            }
+impl<EC: 'static + Debug, EA: 'static + Debug> Builder<EC, EA> {
+    /// Start building an [`Acceptor`] from the provided [`AcmeConfig`].
+    pub fn new(config: AcmeConfig<EC, EA>) -> Self {
+        Self {
+            config,
+            alpn_protocols: None,
+            client_cert_verifier: None,
         }
-    };
-
-    (
-        Acceptor {
-            challenge_server_config,
-            default_server_config,
-        },
-        future,
-    )
+    }
+
+    /// Set the ALPN protocols the TLS listener will advertise, in preference order. For example,
+    /// `vec![b"h2".to_vec(), b"http/1.1".to_vec()]` advertises HTTP/2 to clients that support it,
+    /// falling back to HTTP/1.1 otherwise. By default, no ALPN protocols are advertised on the
+    /// default server config, so clients always negotiate HTTP/1.1.
+    ///
+    /// This only affects the server config used to serve ordinary connections; the server config
+    /// used to answer tls-alpn-01 challenges always advertises the `acme-tls/1` protocol required
+    /// by the challenge, regardless of this setting.
+    pub fn with_alpn_protocols(mut self, alpn_protocols: Vec<Vec<u8>>) -> Self {
+        self.alpn_protocols = Some(alpn_protocols);
+        self
+    }
+
+    /// Require (and verify) a client certificate for mutual TLS, using the provided `verifier`.
+    ///
+    /// Build a `verifier` from a trusted [`RootCertStore`][rustls_acme::futures_rustls::rustls::RootCertStore]
+    /// with `WebPkiClientVerifier::builder` (in
+    /// [`rustls_acme::futures_rustls::rustls::server::WebPkiClientVerifier`]), or supply your own
+    /// [`ClientCertVerifier`] implementation for more control, such as accepting an unauthenticated
+    /// client.
+    ///
+    /// Once this is set, the verified client certificate chain is available through
+    /// [`Transport::peer_certificates`].
+    pub fn with_client_cert_verifier(mut self, verifier: Arc<dyn ClientCertVerifier>) -> Self {
+        self.client_cert_verifier = Some(verifier);
+        self
+    }
+
+    /// Build the [`Acceptor`], the [`AcmeEvent`] stream, and the [`CertStatus`] handle.
+    pub fn build(self) -> (Acceptor, impl Stream<Item = AcmeEvent<EC, EA>>, CertStatus) {
+        let Self {
+            config,
+            alpn_protocols,
+            client_cert_verifier,
+        } = self;
+        let mut state = config.state();
+        let challenge_server_config = state.challenge_rustls_config();
+        let mut default_server_config = state.default_rustls_config();
+        if let Some(verifier) = client_cert_verifier {
+            // Build from the crypto provider already baked into rustls-acme's config, rather
+            // than `ServerConfig::builder()`, which falls back to the process-level default
+            // provider — one that rustls-acme never installs, and that a program using only
+            // this crate's ALPN/mTLS config may never have installed either.
+            let mut server_config = ServerConfig::builder_with_provider(
+                default_server_config.crypto_provider().clone(),
+            )
+            .with_safe_default_protocol_versions()
+            .expect("rustls-acme's own crypto provider supports its own protocol versions")
+            .with_client_cert_verifier(verifier)
+            .with_cert_resolver(default_server_config.cert_resolver.clone());
+            server_config.alpn_protocols = default_server_config.alpn_protocols.clone();
+            default_server_config = Arc::new(server_config);
+        }
+        if let Some(alpn_protocols) = alpn_protocols {
+            let mut server_config = (*default_server_config).clone();
+            server_config.alpn_protocols = alpn_protocols;
+            default_server_config = Arc::new(server_config);
+        }
+        let cert_status = CertStatus::new();
+
+        let events =
+            stream::unfold((state, cert_status.clone()), |(mut state, cert_status)| async move {
+                let event = state.next().await?;
+                if matches!(
+                    event,
+                    Ok(EventOk::DeployedNewCert | EventOk::DeployedCachedCert)
+                ) {
+                    cert_status.set_ready();
+                }
+                Some((event, (state, cert_status)))
+            });
+
+        (
+            Acceptor {
+                challenge_server_config,
+                default_server_config,
+            },
+            events,
+            cert_status,
+        )
+    }
+}
+
+/// Create a new [`Acceptor`] to pass to [`trillium_server_common::Config::with_acceptor`], a
+/// stream of [`AcmeEvent`]s that must be polled (for example by spawning a future that drains it
+/// in a loop) to drive certificate acquisition and renewal forward, and a [`CertStatus`] handle
+/// for observing when the first certificate becomes available.
+///
+/// This is a shorthand for `Builder::new(config).build()`; use [`Builder`] directly to customize
+/// behavior such as the ALPN protocols offered by the TLS listener.
+pub fn new<EC: 'static + Debug, EA: 'static + Debug>(
+    config: AcmeConfig<EC, EA>,
+) -> (Acceptor, impl Stream<Item = AcmeEvent<EC, EA>>, CertStatus) {
+    Builder::new(config).build()
 }
 
 #[async_trait]
@@ -2,6 +2,7 @@ use std::io;
 use std::pin::Pin;
 use std::task::{Context, Poll};
 
+use rustls_acme::futures_rustls::rustls::pki_types::CertificateDer;
 use rustls_acme::futures_rustls::server::TlsStream;
 
 /// This transport either contains a valid TLS stream, or represents a closed connection after
@@ -9,6 +10,19 @@ use rustls_acme::futures_rustls::server::TlsStream;
 #[derive(Debug)]
 pub struct Transport<Input>(pub(crate) Option<TlsStream<Input>>);
 
+impl<Input> Transport<Input> {
+    /// Returns the client's verified certificate chain, if mutual TLS was configured via
+    /// [`crate::Builder::with_client_cert_verifier`] and the client presented a certificate.
+    pub fn peer_certificates(&self) -> Option<Vec<CertificateDer<'static>>> {
+        self.0
+            .as_ref()?
+            .get_ref()
+            .1
+            .peer_certificates()
+            .map(<[_]>::to_vec)
+    }
+}
+
 impl<Input> trillium_server_common::AsyncRead for Transport<Input>
 where
     Input: trillium_server_common::Transport,
@@ -0,0 +1,148 @@
+#![allow(clippy::needless_question_mark)]
+
+use std::fs::File;
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, bail, Context};
+use trillium_acme::rustls_acme::futures_rustls::rustls::{self, ClientConfig, RootCertStore};
+use trillium_acme::{http_challenge_handler, Http01Config, Http01Tokens};
+
+// Retry the provided function until it returns true or 15 seconds have passed. If the latter,
+// return an error.
+fn retry_loop(f: impl Fn() -> anyhow::Result<bool>) -> anyhow::Result<()> {
+    let time = Instant::now();
+    while time.elapsed() <= Duration::from_secs(15) {
+        match f() {
+            Ok(true) => return Ok(()),
+            Ok(false) => (),
+            Err(e) => return Err(e),
+        }
+        std::thread::sleep(Duration::from_millis(1));
+    }
+    bail!("timeout");
+}
+
+struct OnDrop(Option<Box<dyn FnOnce()>>);
+impl Drop for OnDrop {
+    fn drop(&mut self) {
+        if let Some(f) = self.0.take() {
+            f();
+        }
+    }
+}
+fn on_drop(f: impl FnOnce() + 'static) -> OnDrop {
+    OnDrop(Some(Box::new(f)))
+}
+
+fn pem_to_client_config(pem: Vec<u8>) -> anyhow::Result<ClientConfig> {
+    let mut roots = rustls_pemfile::certs(&mut pem.as_slice())
+        .collect::<Result<Vec<_>, _>>()
+        .context("root certificate parsing")?;
+    let root = roots.pop().context("root certificate")?;
+    assert!(roots.is_empty());
+
+    let mut root_store = RootCertStore::empty();
+    root_store.add(root).context("root certificate")?;
+    Ok(ClientConfig::builder()
+        .with_root_certificates(root_store)
+        .with_no_client_auth())
+}
+
+#[test]
+fn test_http01_with_pebble() -> anyhow::Result<()> {
+    rustls::crypto::aws_lc_rs::default_provider()
+        .install_default()
+        .map_err(|_| anyhow!("Failed to install default crypto provider"))?;
+
+    let tempdir = tempfile::tempdir()?;
+
+    // pebble-challtestsrv's own http-01 responder stays disabled: our real listener on the
+    // `httpPort` pebble's config points validation at (see pebble-config.json) answers
+    // challenges instead. We still need its dns01 server, since it's what makes pebble resolve
+    // `http01.example` to 127.0.0.1 in the first place.
+    let log_path = tempdir.path().join("pebble-challtestsrv.log");
+    let mut child = Command::new("pebble-challtestsrv")
+        .args([
+            "-http01",
+            "",
+            "-https01",
+            "",
+            "-tlsalpn01",
+            "",
+            "-doh",
+            "",
+            "-dns01",
+            "127.0.0.1:8053",
+            "-management",
+            "127.0.0.1:8055",
+        ])
+        .stdout(File::create(&log_path)?)
+        .spawn()?;
+    retry_loop(|| {
+        Ok(std::fs::read_to_string(&log_path)
+            .context("reading pebble-challtestsrv log")?
+            .contains("Creating TCP and UDP DNS-01 challenge server on 127.0.0.1:8053"))
+    })
+    .context("waiting for pebble-challtestsrv")?;
+    let _exit_challtestsrv = on_drop(move || child.kill().expect("kill pebble-challtestsrv"));
+    println!("pebble-challtestsrv started");
+
+    let log_path = tempdir.path().join("pebble.log");
+    let mut child = Command::new("pebble")
+        .args([
+            "-dnsserver",
+            "127.0.0.1:8053",
+            "-config",
+            "tests/test-with-pebble/pebble-config.json",
+        ])
+        .env("PEBBLE_VA_NOSLEEP", "1")
+        .stdout(File::create(&log_path)?)
+        .spawn()?;
+    retry_loop(|| {
+        Ok(std::fs::read_to_string(&log_path)
+            .context("reading pebble log")?
+            .contains("ACME directory available at: https://127.0.0.1:14000/dir"))
+    })
+    .context("waiting for pebble")?;
+    let _exit_pebble = on_drop(move || child.kill().expect("kill pebble"));
+    println!("pebble started");
+
+    let pebble_client_config = pem_to_client_config(
+        std::fs::read("tests/test-with-pebble/pebble.minica.pem")
+            .context("reading pebble dir root certificate file")?,
+    )
+    .context("creating client config for pebble")?;
+
+    // A TLS client trusting pebble's own root, for the ACME directory itself.
+    let acme_client = trillium_client::client(trillium_rustls::RustlsConfig::new(
+        pebble_client_config,
+        trillium_smol::ClientConfig::new().with_nodelay(true),
+    ));
+
+    let tokens = Http01Tokens::new();
+    let config = Http01Config::new(acme_client, ["http01.example"], tokens.clone())
+        .contact_push("mailto:admin@example.org")
+        .directory("https://127.0.0.1:14000/dir");
+
+    // Pebble's test config (tests/test-with-pebble/pebble-config.json) points http-01
+    // validation at this fixed `httpPort` rather than the real port 80.
+    trillium_smol::config()
+        .with_port(5002)
+        .with_nodelay()
+        .spawn(http_challenge_handler(tokens));
+
+    let (certificate_chain_pem, private_key_der) =
+        smol::block_on(config.obtain_certificate()).context("obtaining HTTP-01 certificate")?;
+
+    let certificate_chain_pem = String::from_utf8(certificate_chain_pem)
+        .context("certificate chain response was not UTF-8 PEM")?;
+    assert!(
+        certificate_chain_pem.contains("BEGIN CERTIFICATE"),
+        "expected a PEM certificate chain, got: {certificate_chain_pem}"
+    );
+    assert!(!private_key_der.is_empty());
+    println!("Got certificate for http01.example via HTTP-01");
+
+    Ok(())
+}
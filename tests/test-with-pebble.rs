@@ -163,12 +163,13 @@ fn test_with_pebble() -> anyhow::Result<()> {
         .directory("https://127.0.0.1:14000/dir")
         .cache(DirCache::new(acme_cache_path.clone()));
 
-    let (acceptor, future) = trillium_acme::new(config);
+    let (acceptor, mut events, _cert_status) = trillium_acme::new(config);
     let stopper = trillium_smol::Stopper::new();
-    let future = stopper.stop_future(future);
-    trillium_smol::spawn(async {
-        future.await;
-    });
+    trillium_smol::spawn(stopper.stop_future(async move {
+        while let Some(event) = futures_lite::StreamExt::next(&mut events).await {
+            println!("ACME event: {:?}", event);
+        }
+    }));
     const HELLO: &str = "Hello TLS!";
     trillium_smol::config()
         .with_port(5001)